@@ -10,10 +10,18 @@ use log::debug;
 /// The magic number that identifies a valid XYZ binary file.
 const XYZ_MAGIC: &[u8] = b"XYZB";
 
+/// The on-disk layout version. All multi-byte integers and floats are
+/// stored little-endian regardless of host architecture, so this value
+/// only needs bumping when the header or record layout itself changes.
+const XYZ_VERSION: u8 = 1;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Format {
     Xyz,
     XyzMeta,
+    /// Delta + zig-zag varint encoded coordinates, quantized to
+    /// [`DEFAULT_QUANTIZATION_SCALE`]. See [`XyzRecord::write_compressed`].
+    XyzCompressed,
 }
 
 impl From<Format> for u8 {
@@ -21,6 +29,7 @@ impl From<Format> for u8 {
         match value {
             Format::Xyz => 1,
             Format::XyzMeta => 2,
+            Format::XyzCompressed => 3,
         }
     }
 }
@@ -32,11 +41,110 @@ impl TryFrom<u8> for Format {
         match value {
             1 => Ok(Format::Xyz),
             2 => Ok(Format::XyzMeta),
+            3 => Ok(Format::XyzCompressed),
             _ => Err(format!("unknown Format value: {}", value)),
         }
     }
 }
 
+/// Grid resolution (in coordinate units, typically metres) that compressed
+/// coordinates are quantized to before delta + varint encoding. A millimetre
+/// grid keeps reconstruction error well below LiDAR measurement noise.
+const DEFAULT_QUANTIZATION_SCALE: f64 = 0.001;
+
+fn quantize(coord: f64, scale: f64) -> i64 {
+    (coord / scale).round() as i64
+}
+
+fn dequantize(quantized: i64, scale: f64) -> f64 {
+    quantized as f64 * scale
+}
+
+/// Folds a signed integer into an unsigned one so small negative and
+/// positive deltas both encode as small varints.
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Writes `value` as a LEB128-style varint: 7 bits per byte, high bit set
+/// on every byte but the last.
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> std::io::Result<()> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[byte])?;
+            break;
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+    Ok(())
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut buff = [0; 1];
+        reader.read_exact(&mut buff)?;
+        result |= ((buff[0] & 0x7F) as u64) << shift;
+        if buff[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(result)
+}
+
+/// Encodes a value into its fixed on-disk byte representation.
+///
+/// Implemented per-primitive and composed for the record types, so a new
+/// point attribute is added by writing one more impl instead of editing a
+/// monolithic codec.
+trait ToWriter {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()>;
+}
+
+/// Decodes a value from its fixed on-disk byte representation.
+///
+/// Mirrors [`ToWriter`]; readers that don't know about a trailing field
+/// block simply never call the `FromReader` impl that would read it.
+trait FromReader: Sized {
+    fn from_reader<R: Read>(reader: &mut R) -> std::io::Result<Self>;
+}
+
+impl ToWriter for f64 {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl FromReader for f64 {
+    fn from_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut buff = [0; 8];
+        reader.read_exact(&mut buff)?;
+        Ok(f64::from_le_bytes(buff))
+    }
+}
+
+impl ToWriter for u8 {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&[*self])
+    }
+}
+
+impl FromReader for u8 {
+    fn from_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut buff = [0; 1];
+        reader.read_exact(&mut buff)?;
+        Ok(buff[0])
+    }
+}
+
 /// A single record of an observed laser data point needed by the algorithms.
 #[derive(Debug, Clone, PartialEq)]
 pub struct XyzRecord {
@@ -53,72 +161,131 @@ pub struct XyzRecordMeta {
     pub return_number: u8,
 }
 
+impl ToWriter for XyzRecordMeta {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.classification.to_writer(writer)?;
+        self.number_of_returns.to_writer(writer)?;
+        self.return_number.to_writer(writer)?;
+        Ok(())
+    }
+}
+
+impl FromReader for XyzRecordMeta {
+    fn from_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(Self {
+            classification: u8::from_reader(reader)?,
+            number_of_returns: u8::from_reader(reader)?,
+            return_number: u8::from_reader(reader)?,
+        })
+    }
+}
+
 impl XyzRecord {
     fn write<W: Write>(&self, writer: &mut W, format: Format) -> std::io::Result<()> {
         // write the x, y, z coordinates
-        writer.write_all(&self.x.to_ne_bytes())?;
-        writer.write_all(&self.y.to_ne_bytes())?;
-        writer.write_all(&self.z.to_ne_bytes())?;
-
-        // write the classification, number of returns, return number, and intensity
+        self.x.to_writer(writer)?;
+        self.y.to_writer(writer)?;
+        self.z.to_writer(writer)?;
 
+        // write the optional meta block, if this format declares one
         match (format, &self.meta) {
             (Format::Xyz, _) => { //do nothing
             }
-            (Format::XyzMeta, Some(meta)) => {
-                writer.write_all(&[
-                    meta.classification,
-                    meta.number_of_returns,
-                    meta.return_number,
-                ])?;
-            }
+            (Format::XyzMeta, Some(meta)) => meta.to_writer(writer)?,
             (Format::XyzMeta, None) => {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
                     "meta data required for XyzMeta format",
                 ));
             }
+            (Format::XyzCompressed, _) => {
+                panic!("compressed records must go through write_compressed/read_compressed")
+            }
         }
 
         Ok(())
     }
 
     fn read<R: Read>(reader: &mut R, format: Format) -> std::io::Result<Self> {
-        let mut buff = [0; 8];
-        reader.read_exact(&mut buff)?;
-        let x = f64::from_ne_bytes(buff);
-
-        reader.read_exact(&mut buff)?;
-        let y = f64::from_ne_bytes(buff);
-
-        reader.read_exact(&mut buff)?;
-        let z = f64::from_ne_bytes(buff);
+        let x = f64::from_reader(reader)?;
+        let y = f64::from_reader(reader)?;
+        let z = f64::from_reader(reader)?;
 
         let meta = match format {
             Format::Xyz => None,
-            Format::XyzMeta => {
-                let mut buff = [0; 3];
-                reader.read_exact(&mut buff)?;
-                let classification = buff[0];
-                let number_of_returns = buff[1];
-                let return_number = buff[2];
-
-                Some(XyzRecordMeta {
-                    classification,
-                    number_of_returns,
-                    return_number,
-                })
+            Format::XyzMeta => Some(XyzRecordMeta::from_reader(reader)?),
+            Format::XyzCompressed => {
+                panic!("compressed records must go through write_compressed/read_compressed")
             }
         };
 
         Ok(Self { x, y, z, meta })
     }
+
+    /// Writes this record relative to `prev`, the previous record's
+    /// quantized coordinates. The first record of a stream (`prev` is
+    /// `None`) stores its absolute quantized coordinates; `prev` is then
+    /// updated so the next call can take a delta against this record.
+    fn write_compressed<W: Write>(
+        &self,
+        writer: &mut W,
+        prev: &mut Option<(i64, i64, i64)>,
+        scale: f64,
+    ) -> std::io::Result<()> {
+        let quantized = (
+            quantize(self.x, scale),
+            quantize(self.y, scale),
+            quantize(self.z, scale),
+        );
+
+        let (dx, dy, dz) = match *prev {
+            Some((px, py, pz)) => (quantized.0 - px, quantized.1 - py, quantized.2 - pz),
+            None => quantized,
+        };
+
+        write_varint(writer, zigzag_encode(dx))?;
+        write_varint(writer, zigzag_encode(dy))?;
+        write_varint(writer, zigzag_encode(dz))?;
+
+        *prev = Some(quantized);
+        Ok(())
+    }
+
+    /// Inverse of [`write_compressed`](Self::write_compressed): reconstructs
+    /// absolute quantized coordinates by summing the decoded delta onto
+    /// `prev`, then dequantizes back to `f64`.
+    fn read_compressed<R: Read>(
+        reader: &mut R,
+        prev: &mut Option<(i64, i64, i64)>,
+        scale: f64,
+    ) -> std::io::Result<Self> {
+        let dx = zigzag_decode(read_varint(reader)?);
+        let dy = zigzag_decode(read_varint(reader)?);
+        let dz = zigzag_decode(read_varint(reader)?);
+
+        let quantized = match *prev {
+            Some((px, py, pz)) => (px + dx, py + dy, pz + dz),
+            None => (dx, dy, dz),
+        };
+        *prev = Some(quantized);
+
+        Ok(Self {
+            x: dequantize(quantized.0, scale),
+            y: dequantize(quantized.1, scale),
+            z: dequantize(quantized.2, scale),
+            meta: None,
+        })
+    }
 }
 
+/// Writes a stream of [`XyzRecord`]s. `W` just needs `Write + Seek`, so this
+/// also works on top of [`crate::io::yaz0::Yaz0Writer`] for archival.
 pub struct XyzInternalWriter<W: Write + Seek> {
     inner: Option<W>,
     records_written: u64,
     format: Format,
+    // previous quantized point, used to delta-encode Format::XyzCompressed
+    prev_quantized: Option<(i64, i64, i64)>,
     // for stats
     start: Option<Instant>,
 }
@@ -137,6 +304,7 @@ impl<W: Write + Seek> XyzInternalWriter<W> {
             inner: Some(inner),
             records_written: 0,
             format,
+            prev_quantized: None,
             start: None,
         }
     }
@@ -154,12 +322,22 @@ impl<W: Write + Seek> XyzInternalWriter<W> {
             self.start = Some(Instant::now());
 
             inner.write_all(XYZ_MAGIC)?;
-            inner.write_all(&[self.format.into()])?;
+            inner.write_all(&[self.format.into(), XYZ_VERSION])?;
             // Write the temporary number of records as all FF
-            inner.write_all(&u64::MAX.to_ne_bytes())?;
+            inner.write_all(&u64::MAX.to_le_bytes())?;
+            if let Format::XyzCompressed = self.format {
+                inner.write_all(&DEFAULT_QUANTIZATION_SCALE.to_le_bytes())?;
+            }
         }
 
-        record.write(inner, self.format)?;
+        match self.format {
+            Format::XyzCompressed => record.write_compressed(
+                inner,
+                &mut self.prev_quantized,
+                DEFAULT_QUANTIZATION_SCALE,
+            )?,
+            _ => record.write(inner, self.format)?,
+        }
         self.records_written += 1;
         Ok(())
     }
@@ -172,9 +350,9 @@ impl<W: Write + Seek> XyzInternalWriter<W> {
             )
         })?;
 
-        // seek to the beginning of the file and write the number of records
-        inner.seek(std::io::SeekFrom::Start(XYZ_MAGIC.len() as u64 + 1))?;
-        inner.write_all(&self.records_written.to_ne_bytes())?;
+        // seek past the magic, format and version bytes, then write the number of records
+        inner.seek(std::io::SeekFrom::Start(XYZ_MAGIC.len() as u64 + 2))?;
+        inner.write_all(&self.records_written.to_le_bytes())?;
 
         // log statistics about the written records
         if let Some(start) = self.start {
@@ -198,11 +376,18 @@ impl<W: Write + Seek> Drop for XyzInternalWriter<W> {
     }
 }
 
+/// Reads a stream of [`XyzRecord`]s. `R` just needs `Read`, so this also
+/// works directly on top of [`crate::io::yaz0::Yaz0Reader`] for
+/// transparent decompression.
 pub struct XyzInternalReader<R: Read> {
     inner: R,
     format: Format,
     n_records: u64,
     records_read: u64,
+    // quantization scale for Format::XyzCompressed, read from the header
+    quantization_scale: Option<f64>,
+    // previous quantized point, used to reconstruct Format::XyzCompressed deltas
+    prev_quantized: Option<(i64, i64, i64)>,
     // for stats
     start: Option<Instant>,
 }
@@ -215,32 +400,72 @@ impl XyzInternalReader<BufReader<File>> {
     }
 }
 
+/// Byte length of the fixed `XYZB` header: magic + format + version +
+/// record count. `Format::XyzCompressed` carries an extra quantization
+/// scale field right after this, but the fixed-width formats don't.
+const XYZ_HEADER_LEN: u64 = (XYZ_MAGIC.len() + 1 + 1 + 8) as u64;
+
+/// Reads and validates the magic number, format and version, returning
+/// `(format, n_records)`. Shared by every reader so the header layout is
+/// only parsed in one place.
+fn read_header<R: Read>(inner: &mut R) -> std::io::Result<(Format, u64)> {
+    // read and check the magic number
+    let mut buff = [0; XYZ_MAGIC.len()];
+    inner.read_exact(&mut buff)?;
+    if buff != XYZ_MAGIC {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "invalid magic number",
+        ));
+    }
+
+    // read and parse the format
+    let mut buff = [0; 1];
+    inner.read_exact(&mut buff)?;
+    let format = buff[0].try_into().expect("should have known format");
+
+    // read and check the layout version; older native-endian files don't
+    // carry this byte at all, so there's nothing sensible to migrate from
+    inner.read_exact(&mut buff)?;
+    let version = buff[0];
+    if version != XYZ_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "unsupported XYZB version {} (expected {})",
+                version, XYZ_VERSION
+            ),
+        ));
+    }
+
+    // read the number of records, defined by a little-endian u64
+    let mut buff = [0; 8];
+    inner.read_exact(&mut buff)?;
+    let n_records = u64::from_le_bytes(buff);
+
+    Ok((format, n_records))
+}
+
 impl<R: Read> XyzInternalReader<R> {
     pub fn new(mut inner: R) -> std::io::Result<Self> {
-        // read and check the magic number
-        let mut buff = [0; XYZ_MAGIC.len()];
-        inner.read_exact(&mut buff)?;
-        if buff != XYZ_MAGIC {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "invalid magic number",
-            ));
-        }
-
-        // read and parse the format
-        let mut buff = [0; 1];
-        inner.read_exact(&mut buff)?;
-        let format = buff[0].try_into().expect("should have known format");
+        let (format, n_records) = read_header(&mut inner)?;
+
+        // compressed streams carry the quantization scale right after the header
+        let quantization_scale = if let Format::XyzCompressed = format {
+            let mut buff = [0; 8];
+            inner.read_exact(&mut buff)?;
+            Some(f64::from_le_bytes(buff))
+        } else {
+            None
+        };
 
-        // read the number of records, defined by the first u64
-        let mut buff = [0; 8];
-        inner.read_exact(&mut buff)?;
-        let n_records = u64::from_ne_bytes(buff);
         Ok(Self {
             inner,
             format,
             n_records,
             records_read: 0,
+            quantization_scale,
+            prev_quantized: None,
             start: None,
         })
     }
@@ -266,7 +491,15 @@ impl<R: Read> XyzInternalReader<R> {
             self.start = Some(Instant::now());
         }
 
-        let record = XyzRecord::read(&mut self.inner, self.format)?;
+        let record = match self.format {
+            Format::XyzCompressed => XyzRecord::read_compressed(
+                &mut self.inner,
+                &mut self.prev_quantized,
+                self.quantization_scale
+                    .expect("quantization scale set for compressed format"),
+            )?,
+            _ => XyzRecord::read(&mut self.inner, self.format)?,
+        };
         self.records_read += 1;
         Ok(Some(record))
     }
@@ -274,6 +507,148 @@ impl<R: Read> XyzInternalReader<R> {
     pub fn format(&self) -> Format {
         self.format
     }
+
+    /// Returns an iterator over owned batches of up to `n` records (capped
+    /// at [`MAX_BATCH`]), so downstream grid-binning can be parallelized
+    /// across worker threads without re-reading the file.
+    pub fn chunks(&mut self, n: usize) -> XyzChunks<'_, R> {
+        XyzChunks {
+            reader: self,
+            batch_size: n.min(MAX_BATCH),
+        }
+    }
+}
+
+impl<R: Read> Iterator for XyzInternalReader<R> {
+    type Item = std::io::Result<XyzRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match XyzInternalReader::next(self) {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.n_records - self.records_read) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Upper bound on records per [`XyzInternalReader::chunks`] batch (64 Ki
+/// records, not bytes), so the memory held by one batch stays bounded.
+const MAX_BATCH: usize = 65536;
+
+/// Iterator over owned record batches, returned by
+/// [`XyzInternalReader::chunks`].
+pub struct XyzChunks<'a, R: Read> {
+    reader: &'a mut XyzInternalReader<R>,
+    batch_size: usize,
+}
+
+impl<R: Read> Iterator for XyzChunks<'_, R> {
+    type Item = std::io::Result<Vec<XyzRecord>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        for _ in 0..self.batch_size {
+            match self.reader.next() {
+                Ok(Some(record)) => batch.push(record),
+                Ok(None) => break,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        if batch.is_empty() {
+            None
+        } else {
+            Some(Ok(batch))
+        }
+    }
+}
+
+impl Format {
+    /// Byte width of one record in this format, or `None` for
+    /// `XyzCompressed`, whose records are variable-length.
+    fn fixed_record_width(&self) -> Option<u64> {
+        match self {
+            Format::Xyz => Some(24),
+            Format::XyzMeta => Some(27),
+            Format::XyzCompressed => None,
+        }
+    }
+}
+
+/// Random-access reader for the fixed-width `Xyz`/`XyzMeta` formats: since
+/// every record has the same byte width, any record can be reached with a
+/// single seek instead of scanning from the start. Useful for tiling code
+/// that only needs one subrange of a large cache.
+pub struct XyzIndexedReader<R: Read + Seek> {
+    inner: R,
+    format: Format,
+    n_records: u64,
+    record_width: u64,
+}
+
+impl XyzIndexedReader<BufReader<File>> {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        debug!("Opening records for random access from: {:?}", path);
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> XyzIndexedReader<R> {
+    pub fn new(mut inner: R) -> std::io::Result<Self> {
+        let (format, n_records) = read_header(&mut inner)?;
+
+        let record_width = format.fixed_record_width().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "XyzIndexedReader requires a fixed-width format (Xyz or XyzMeta)",
+            )
+        })?;
+
+        Ok(Self {
+            inner,
+            format,
+            n_records,
+            record_width,
+        })
+    }
+
+    /// Number of records in the file.
+    pub fn len(&self) -> u64 {
+        self.n_records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n_records == 0
+    }
+
+    /// Seeks the underlying reader to the start of record `index`.
+    pub fn seek_to_record(&mut self, index: u64) -> std::io::Result<()> {
+        if index >= self.n_records {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "record index {} out of bounds ({} records)",
+                    index, self.n_records
+                ),
+            ));
+        }
+
+        let offset = XYZ_HEADER_LEN + index * self.record_width;
+        self.inner.seek(std::io::SeekFrom::Start(offset))?;
+        Ok(())
+    }
+
+    /// Seeks to and reads record `index`.
+    pub fn get(&mut self, index: u64) -> std::io::Result<XyzRecord> {
+        self.seek_to_record(index)?;
+        XyzRecord::read(&mut self.inner, self.format)
+    }
 }
 
 #[cfg(test)]
@@ -304,6 +679,71 @@ mod test {
         assert_eq!(record, read_record);
     }
 
+    #[test]
+    fn test_meta_to_writer_from_reader_roundtrip() {
+        let meta = XyzRecordMeta {
+            classification: 4,
+            number_of_returns: 5,
+            return_number: 6,
+        };
+
+        let mut buff = Vec::new();
+        meta.to_writer(&mut buff).unwrap();
+        assert_eq!(buff, vec![4, 5, 6]);
+
+        let read_meta = XyzRecordMeta::from_reader(&mut buff.as_slice()).unwrap();
+        assert_eq!(meta, read_meta);
+    }
+
+    #[test]
+    fn test_xyz_record_fixed_byte_layout() {
+        let record = XyzRecord {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            meta: Some(XyzRecordMeta {
+                classification: 4,
+                number_of_returns: 5,
+                return_number: 6,
+            }),
+        };
+
+        let mut buff = Vec::new();
+        record.write(&mut buff, Format::XyzMeta).unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        expected.extend_from_slice(&2.0f64.to_le_bytes());
+        expected.extend_from_slice(&3.0f64.to_le_bytes());
+        expected.extend_from_slice(&[4, 5, 6]);
+
+        assert_eq!(buff, expected);
+    }
+
+    #[test]
+    fn test_header_fixed_byte_layout() {
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = XyzInternalWriter::new(cursor, Format::Xyz);
+        writer
+            .write_record(&XyzRecord {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                meta: None,
+            })
+            .unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(XYZ_MAGIC);
+        expected.push(Format::Xyz.into());
+        expected.push(XYZ_VERSION);
+        expected.extend_from_slice(&1u64.to_le_bytes());
+        expected.extend_from_slice(&[0u8; 24]);
+
+        assert_eq!(data, expected);
+    }
+
     #[test]
     fn test_writer_reader_many() {
         let cursor = Cursor::new(Vec::new());
@@ -333,4 +773,156 @@ mod test {
         assert_eq!(reader.next().unwrap().unwrap(), record);
         assert_eq!(reader.next().unwrap(), None);
     }
+
+    #[test]
+    fn test_zigzag_roundtrip() {
+        for n in [0i64, 1, -1, 2, -2, i64::MAX, i64::MIN, 12345, -12345] {
+            assert_eq!(zigzag_decode(zigzag_encode(n)), n);
+        }
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for n in [0u64, 1, 127, 128, 16383, 16384, u64::MAX] {
+            let mut buff = Vec::new();
+            write_varint(&mut buff, n).unwrap();
+            assert_eq!(read_varint(&mut buff.as_slice()).unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn test_compressed_writer_reader_roundtrip() {
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = XyzInternalWriter::new(cursor, Format::XyzCompressed);
+
+        // two nearby points (small deltas), then a far jump with negative deltas
+        let records = vec![
+            XyzRecord {
+                x: 123.456,
+                y: 789.012,
+                z: 10.0,
+                meta: None,
+            },
+            XyzRecord {
+                x: 123.457,
+                y: 789.010,
+                z: 10.001,
+                meta: None,
+            },
+            XyzRecord {
+                x: 100.0,
+                y: 200.0,
+                z: -5.0,
+                meta: None,
+            },
+        ];
+
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+
+        let data = writer.finish().unwrap().into_inner();
+        let mut reader = super::XyzInternalReader::new(Cursor::new(data)).unwrap();
+
+        for record in &records {
+            let read_record = reader.next().unwrap().unwrap();
+            assert!((read_record.x - record.x).abs() < DEFAULT_QUANTIZATION_SCALE);
+            assert!((read_record.y - record.y).abs() < DEFAULT_QUANTIZATION_SCALE);
+            assert!((read_record.z - record.z).abs() < DEFAULT_QUANTIZATION_SCALE);
+        }
+        assert_eq!(reader.next().unwrap(), None);
+    }
+
+    #[test]
+    fn test_indexed_reader_seeks_backward_and_forward() {
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = XyzInternalWriter::new(cursor, Format::XyzMeta);
+
+        let records: Vec<_> = (0..5)
+            .map(|i| XyzRecord {
+                x: i as f64,
+                y: i as f64 * 2.0,
+                z: i as f64 * 3.0,
+                meta: Some(XyzRecordMeta {
+                    classification: i as u8,
+                    number_of_returns: 1,
+                    return_number: 1,
+                }),
+            })
+            .collect();
+
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut reader = XyzIndexedReader::new(Cursor::new(data)).unwrap();
+        assert_eq!(reader.len(), 5);
+
+        // forward
+        assert_eq!(reader.get(0).unwrap(), records[0]);
+        assert_eq!(reader.get(3).unwrap(), records[3]);
+        // backward, then forward again
+        assert_eq!(reader.get(1).unwrap(), records[1]);
+        assert_eq!(reader.get(4).unwrap(), records[4]);
+
+        assert!(reader.get(5).is_err());
+    }
+
+    #[test]
+    fn test_iterator_impl() {
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = XyzInternalWriter::new(cursor, Format::Xyz);
+
+        let records: Vec<_> = (0..4)
+            .map(|i| XyzRecord {
+                x: i as f64,
+                y: i as f64,
+                z: i as f64,
+                meta: None,
+            })
+            .collect();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        let reader = XyzInternalReader::new(Cursor::new(data)).unwrap();
+        assert_eq!(reader.size_hint(), (4, Some(4)));
+
+        let collected: Vec<_> = reader.map(|r| r.unwrap()).collect();
+        assert_eq!(collected, records);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = XyzInternalWriter::new(cursor, Format::Xyz);
+
+        let records: Vec<_> = (0..5)
+            .map(|i| XyzRecord {
+                x: i as f64,
+                y: i as f64,
+                z: i as f64,
+                meta: None,
+            })
+            .collect();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut reader = XyzInternalReader::new(Cursor::new(data)).unwrap();
+        let batches: Vec<Vec<XyzRecord>> =
+            reader.chunks(2).collect::<std::io::Result<_>>().unwrap();
+
+        assert_eq!(
+            batches,
+            vec![
+                records[0..2].to_vec(),
+                records[2..4].to_vec(),
+                records[4..5].to_vec(),
+            ]
+        );
+    }
 }
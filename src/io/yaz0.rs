@@ -0,0 +1,311 @@
+//! Yaz0-style LZ77 block compression, used to archive intermediate point
+//! caches. `Yaz0Writer` buffers everything written to it and compresses on
+//! [`Yaz0Writer::finish`]; `Yaz0Reader` decompresses a Yaz0 stream on the
+//! fly and implements [`Read`], so it can sit transparently underneath
+//! anything that only needs a byte stream (e.g. `XyzInternalReader`).
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+};
+
+/// The magic number that identifies a Yaz0-compressed stream.
+const YAZ0_MAGIC: &[u8] = b"Yaz0";
+
+/// Back-references can point at most this far behind the current position.
+const WINDOW_SIZE: usize = 0x1000;
+/// Longest match a single back-reference token can encode (3-byte form).
+const MAX_MATCH_LEN: usize = 0xFF + 0x12;
+/// Shortest run worth encoding as a back-reference instead of literals.
+const MIN_MATCH_LEN: usize = 3;
+
+/// Buffers written bytes and compresses them into a Yaz0 container once
+/// [`finish`](Self::finish) is called. Implements `Write`/`Seek` over an
+/// internal buffer, so it's a drop-in sink for any writer that needs
+/// random-access patching before the final bytes are known (like
+/// `XyzInternalWriter`'s record-count backpatch).
+pub struct Yaz0Writer<W: Write> {
+    inner: W,
+    buffer: Cursor<Vec<u8>>,
+}
+
+impl<W: Write> Yaz0Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: Cursor::new(Vec::new()),
+        }
+    }
+
+    /// Compresses the buffered bytes and writes the Yaz0 container
+    /// (magic + uncompressed size + compressed groups) to `inner`.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let data = self.buffer.into_inner();
+        self.inner.write_all(YAZ0_MAGIC)?;
+        self.inner.write_all(&(data.len() as u32).to_be_bytes())?;
+        compress(&data, &mut self.inner)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Yaz0Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.buffer.flush()
+    }
+}
+
+impl<W: Write> Seek for Yaz0Writer<W> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.buffer.seek(pos)
+    }
+}
+
+/// Greedy LZ77 match finder: a hash map from 3-byte prefix to the most
+/// recent position it was seen at, chained through `prev` for older
+/// occurrences within the window.
+fn compress<W: Write>(data: &[u8], out: &mut W) -> std::io::Result<()> {
+    let mut heads: HashMap<[u8; 3], usize> = HashMap::new();
+    let mut prev: Vec<Option<usize>> = vec![None; data.len()];
+
+    let mut pos = 0;
+    let mut group_flags = 0u8;
+    let mut group_bits = 0u8;
+    let mut group_tokens: Vec<u8> = Vec::new();
+
+    while pos < data.len() {
+        let best_match = find_match(data, pos, &heads, &prev);
+
+        // record this position for future matches before advancing
+        if pos + 3 <= data.len() {
+            let key = [data[pos], data[pos + 1], data[pos + 2]];
+            prev[pos] = heads.insert(key, pos);
+        }
+
+        match best_match {
+            Some((distance, length)) if length >= MIN_MATCH_LEN => {
+                // a back-reference token: flag bit stays 0
+                group_flags <<= 1;
+                group_bits += 1;
+
+                if length <= 17 {
+                    let byte0 = (((length - 2) as u8) << 4) | ((distance - 1) >> 8) as u8;
+                    let byte1 = ((distance - 1) & 0xFF) as u8;
+                    group_tokens.push(byte0);
+                    group_tokens.push(byte1);
+                } else {
+                    let byte0 = ((distance - 1) >> 8) as u8;
+                    let byte1 = ((distance - 1) & 0xFF) as u8;
+                    let byte2 = (length - 0x12) as u8;
+                    group_tokens.push(byte0);
+                    group_tokens.push(byte1);
+                    group_tokens.push(byte2);
+                }
+
+                // index the skipped positions too, so later matches can reach into this run
+                for skip_pos in (pos + 1)..(pos + length).min(data.len()) {
+                    if skip_pos + 3 <= data.len() {
+                        let key = [data[skip_pos], data[skip_pos + 1], data[skip_pos + 2]];
+                        prev[skip_pos] = heads.insert(key, skip_pos);
+                    }
+                }
+
+                pos += length;
+            }
+            _ => {
+                // a literal token: flag bit is 1
+                group_flags = (group_flags << 1) | 1;
+                group_bits += 1;
+                group_tokens.push(data[pos]);
+                pos += 1;
+            }
+        }
+
+        if group_bits == 8 {
+            out.write_all(&[group_flags])?;
+            out.write_all(&group_tokens)?;
+            group_flags = 0;
+            group_bits = 0;
+            group_tokens.clear();
+        }
+    }
+
+    if group_bits > 0 {
+        group_flags <<= 8 - group_bits;
+        out.write_all(&[group_flags])?;
+        out.write_all(&group_tokens)?;
+    }
+
+    Ok(())
+}
+
+fn find_match(
+    data: &[u8],
+    pos: usize,
+    heads: &HashMap<[u8; 3], usize>,
+    prev: &[Option<usize>],
+) -> Option<(usize, usize)> {
+    if pos + MIN_MATCH_LEN > data.len() {
+        return None;
+    }
+
+    let key = [data[pos], data[pos + 1], data[pos + 2]];
+    let mut candidate = heads.get(&key).copied();
+    let mut best: Option<(usize, usize)> = None;
+
+    while let Some(cand_pos) = candidate {
+        if pos - cand_pos > WINDOW_SIZE {
+            break;
+        }
+
+        let max_len = MAX_MATCH_LEN.min(data.len() - pos);
+        let mut len = 0;
+        while len < max_len && data[cand_pos + len] == data[pos + len] {
+            len += 1;
+        }
+
+        if len >= MIN_MATCH_LEN && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((pos - cand_pos, len));
+        }
+
+        candidate = prev[cand_pos];
+    }
+
+    best
+}
+
+/// Decompresses a Yaz0 stream on the fly, producing plain decompressed
+/// bytes through the `Read` implementation.
+pub struct Yaz0Reader<R: Read> {
+    inner: R,
+    uncompressed_size: u64,
+    output: Vec<u8>,
+    read_pos: usize,
+    group_flags: u8,
+    group_bits_left: u8,
+}
+
+impl<R: Read> Yaz0Reader<R> {
+    pub fn new(mut inner: R) -> std::io::Result<Self> {
+        let mut magic = [0; 4];
+        inner.read_exact(&mut magic)?;
+        if magic != YAZ0_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid Yaz0 magic number",
+            ));
+        }
+
+        let mut size_buf = [0; 4];
+        inner.read_exact(&mut size_buf)?;
+        let uncompressed_size = u32::from_be_bytes(size_buf) as u64;
+
+        Ok(Self {
+            inner,
+            uncompressed_size,
+            output: Vec::with_capacity(uncompressed_size as usize),
+            read_pos: 0,
+            group_flags: 0,
+            group_bits_left: 0,
+        })
+    }
+
+    fn decode_next_byte(&mut self) -> std::io::Result<bool> {
+        if self.output.len() as u64 >= self.uncompressed_size {
+            return Ok(false);
+        }
+
+        if self.group_bits_left == 0 {
+            let mut flag_buf = [0; 1];
+            self.inner.read_exact(&mut flag_buf)?;
+            self.group_flags = flag_buf[0];
+            self.group_bits_left = 8;
+        }
+
+        let is_literal = self.group_flags & 0x80 != 0;
+        self.group_flags <<= 1;
+        self.group_bits_left -= 1;
+
+        if is_literal {
+            let mut byte = [0; 1];
+            self.inner.read_exact(&mut byte)?;
+            self.output.push(byte[0]);
+        } else {
+            let mut pair = [0; 2];
+            self.inner.read_exact(&mut pair)?;
+            let length_nibble = pair[0] >> 4;
+            let length = if length_nibble == 0 {
+                let mut extra = [0; 1];
+                self.inner.read_exact(&mut extra)?;
+                extra[0] as usize + 0x12
+            } else {
+                length_nibble as usize + 2
+            };
+            let distance = (((pair[0] & 0x0F) as usize) << 8 | pair[1] as usize) + 1;
+
+            let start = self.output.len() - distance;
+            for i in 0..length {
+                let byte = self.output[start + i];
+                self.output.push(byte);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Yaz0Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.read_pos >= self.output.len() && self.decode_next_byte()? {}
+
+        let available = &self.output[self.read_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.read_pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn roundtrip(data: &[u8]) -> Vec<u8> {
+        let writer = Yaz0Writer::new(Cursor::new(Vec::new()));
+        let mut writer = writer;
+        writer.write_all(data).unwrap();
+        let compressed = writer.finish().unwrap().into_inner();
+
+        let mut reader = Yaz0Reader::new(Cursor::new(compressed)).unwrap();
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed).unwrap();
+        decompressed
+    }
+
+    #[test]
+    fn test_roundtrip_literals_only() {
+        let data = b"the quick brown fox jumps over the lazy dog".to_vec();
+        assert_eq!(roundtrip(&data), data);
+    }
+
+    #[test]
+    fn test_roundtrip_repeated_pattern() {
+        let data = b"abcabcabcabcabcabcabcabcabcabcabcabcabcabc".to_vec();
+        assert_eq!(roundtrip(&data), data);
+    }
+
+    #[test]
+    fn test_roundtrip_long_run() {
+        let data = vec![0x42u8; 1000];
+        assert_eq!(roundtrip(&data), data);
+    }
+
+    #[test]
+    fn test_roundtrip_empty() {
+        let data: Vec<u8> = Vec::new();
+        assert_eq!(roundtrip(&data), data);
+    }
+}